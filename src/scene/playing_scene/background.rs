@@ -0,0 +1,158 @@
+//! Animated backdrop drawn behind the guidelines, waterfall and keyboard.
+//!
+//! The effect lives on the shared pipeline's background layer and is advanced
+//! from `delta` so it keeps moving even while playback is paused.
+
+use std::time::Duration;
+
+use neothesia_pipelines::quad::QuadInstance;
+use wgpu_jumpstart::Color;
+
+/// Number of horizontal bands the gradient pulse is drawn with.
+const BANDS: usize = 16;
+/// Number of drifting particles.
+const PARTICLE_COUNT: usize = 64;
+
+/// Which backdrop to draw, or none at all.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BackgroundEffect {
+    /// A slow vertical gradient that pulses in brightness.
+    GradientPulse,
+    /// Parallax particles drifting upwards.
+    Particles,
+    /// Keep the static clear color.
+    None,
+}
+
+impl Default for BackgroundEffect {
+    fn default() -> Self {
+        Self::GradientPulse
+    }
+}
+
+struct Particle {
+    x: f32,
+    y: f32,
+    speed: f32,
+    size: f32,
+}
+
+pub struct AnimatedBackground {
+    effect: BackgroundEffect,
+    animation_speed: f32,
+
+    width: f32,
+    height: f32,
+
+    // Advanced by `delta` every frame so the motion is independent of playback.
+    elapsed: f32,
+    particles: Vec<Particle>,
+
+    cache: Vec<QuadInstance>,
+}
+
+impl AnimatedBackground {
+    pub fn new(config: &crate::config::Config, width: f32, height: f32) -> Self {
+        let mut this = Self {
+            effect: config.background_effect,
+            animation_speed: config.animation_speed,
+            width,
+            height,
+            elapsed: 0.0,
+            particles: Vec::new(),
+            cache: Vec::new(),
+        };
+        this.seed_particles();
+        this
+    }
+
+    /// Largest number of instances any effect can emit in a frame, so the
+    /// scene can size the shared pipeline's background layer to never overflow
+    /// regardless of which effect is selected.
+    pub const fn instance_capacity() -> usize {
+        if BANDS > PARTICLE_COUNT {
+            BANDS
+        } else {
+            PARTICLE_COUNT
+        }
+    }
+
+    pub fn resize(&mut self, width: f32, height: f32) {
+        self.width = width;
+        self.height = height;
+        self.seed_particles();
+    }
+
+    /// Advance the animation and rebuild the instance buffer. `time` is the
+    /// current playback position; only the gradient pulse uses it.
+    pub fn update(&mut self, delta: Duration, time: Duration) -> &[QuadInstance] {
+        self.elapsed += delta.as_secs_f32() * self.animation_speed;
+        self.cache.clear();
+
+        match self.effect {
+            BackgroundEffect::GradientPulse => self.gradient_pulse(time),
+            BackgroundEffect::Particles => self.drift_particles(delta),
+            BackgroundEffect::None => {}
+        }
+
+        &self.cache
+    }
+
+    fn gradient_pulse(&mut self, time: Duration) {
+        // Advance the pulse phase with the playback time as well as `elapsed`.
+        let phase = self.elapsed + time.as_secs_f32() * 0.5;
+        let band_height = self.height / BANDS as f32;
+
+        for band in 0..BANDS {
+            let t = band as f32 / BANDS as f32;
+            let pulse = 0.5 + 0.5 * (phase + t * std::f32::consts::TAU).sin();
+            let value = (12.0 + 20.0 * t * pulse) as u8;
+
+            self.cache.push(QuadInstance {
+                position: [0.0, band as f32 * band_height],
+                size: [self.width, band_height],
+                color: Color::from_rgba8(value, value / 2, value + 8, 1.0).into_linear_rgba(),
+                ..Default::default()
+            });
+        }
+    }
+
+    fn drift_particles(&mut self, delta: Duration) {
+        let dt = delta.as_secs_f32() * self.animation_speed;
+
+        for p in self.particles.iter_mut() {
+            p.y -= p.speed * dt;
+            if p.y + p.size < 0.0 {
+                p.y = self.height + p.size;
+            }
+
+            self.cache.push(QuadInstance {
+                position: [p.x, p.y],
+                size: [p.size, p.size],
+                color: Color::from_rgba8(80, 80, 110, 0.25).into_linear_rgba(),
+                ..Default::default()
+            });
+        }
+    }
+
+    fn seed_particles(&mut self) {
+        self.particles.clear();
+        // Deterministic scatter so resizes stay stable between frames.
+        let mut state: u32 = 0x9e37_79b9;
+        let mut next = || {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            (state as f32) / (u32::MAX as f32)
+        };
+
+        for _ in 0..PARTICLE_COUNT {
+            self.particles.push(Particle {
+                x: next() * self.width,
+                y: next() * self.height,
+                speed: 10.0 + next() * 40.0,
+                size: 1.0 + next() * 3.0,
+            });
+        }
+    }
+}
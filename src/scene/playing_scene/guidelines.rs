@@ -0,0 +1,178 @@
+use std::time::Duration;
+
+use neothesia_pipelines::quad::QuadInstance;
+use wgpu_jumpstart::Color;
+
+use crate::target::Target;
+
+/// Faint grid that sits behind the waterfall to give the falling notes a sense
+/// of rhythm. Vertical lines are aligned to the octave boundaries of the
+/// keyboard layout, horizontal lines scroll with the tempo map.
+pub struct Guidelines {
+    keyboard_layout: piano_math::KeyboardLayout,
+
+    vertical: bool,
+    horizontal: bool,
+
+    cache: Vec<QuadInstance>,
+}
+
+impl Guidelines {
+    pub fn new(
+        keyboard_layout: piano_math::KeyboardLayout,
+        config: &crate::config::Config,
+    ) -> Self {
+        Self {
+            keyboard_layout,
+            vertical: config.vertical_guidelines,
+            horizontal: config.horizontal_guidelines,
+            cache: Vec::new(),
+        }
+    }
+
+    pub fn set_layout(&mut self, keyboard_layout: piano_math::KeyboardLayout) {
+        self.keyboard_layout = keyboard_layout;
+    }
+
+    /// Re-build the instance buffer for the current playback time and return it
+    /// so the scene can upload it to the shared pipeline's background layer.
+    pub fn update(&mut self, target: &Target, time: Duration) -> &[QuadInstance] {
+        self.cache.clear();
+
+        if self.vertical {
+            self.push_vertical(target.window_state.logical_size.height);
+        }
+
+        if self.horizontal {
+            self.push_horizontal(target, time);
+        }
+
+        &self.cache
+    }
+
+    fn push_vertical(&mut self, height: f32) {
+        for key in self.keyboard_layout.keys.iter() {
+            // Octave boundaries live on every C (note id 0 within the octave).
+            if key.note_id() != 0 {
+                continue;
+            }
+
+            // MIDI note 60 (middle C) sits in `id/12 == 5`; the `-1` octave
+            // labelling is the same one `layout.rs` uses for its key ranges.
+            let octave = key.id() / 12;
+            let color = if octave == 5 {
+                // Middle C gets the brightest line.
+                Color::from_rgba8(86, 86, 96, 0.9)
+            } else {
+                Color::from_rgba8(56, 56, 64, 0.6)
+            };
+
+            self.cache.push(QuadInstance {
+                position: [key.x(), 0.0],
+                size: [1.0, height],
+                color: color.into_linear_rgba(),
+                ..Default::default()
+            });
+        }
+    }
+
+    fn push_horizontal(&mut self, target: &Target, time: Duration) {
+        let Some(midi) = target.midi_file.as_ref() else {
+            return;
+        };
+
+        let height = target.window_state.logical_size.height;
+        let width = target.window_state.logical_size.width;
+        let span = target.config.animation_span().as_secs_f32();
+
+        let now = time.as_secs_f32();
+        let end = now + span;
+        let keyboard_top = height - height * 0.2;
+
+        for beat in beats(midi) {
+            let beat_time = beat.time.as_secs_f32();
+            if beat_time < now || beat_time > end {
+                continue;
+            }
+
+            // Same mapping the waterfall uses: the playhead sits on the keyboard,
+            // future events rise towards the top of the window.
+            let progress = (beat_time - now) / span;
+            let y = keyboard_top - progress * keyboard_top;
+
+            let (thickness, alpha) = match beat.strength {
+                BeatStrength::Bar => (2.0, 0.45),
+                BeatStrength::Beat => (1.0, 0.3),
+                BeatStrength::Subdivision => (1.0, 0.15),
+            };
+
+            self.cache.push(QuadInstance {
+                position: [0.0, y],
+                size: [width, thickness],
+                color: Color::from_rgba8(120, 120, 130, alpha).into_linear_rgba(),
+                ..Default::default()
+            });
+        }
+    }
+}
+
+/// Relative emphasis of a guideline, driving its opacity and thickness.
+enum BeatStrength {
+    Bar,
+    Beat,
+    Subdivision,
+}
+
+/// A single beat position derived from the tempo map.
+struct Beat {
+    time: Duration,
+    strength: BeatStrength,
+}
+
+/// Walk the file's measures and subdivide each bar into beats so the grid can
+/// fade downbeats, beats and subdivisions differently.
+fn beats(midi: &midi_file::MidiFile) -> impl Iterator<Item = Beat> + '_ {
+    const BEATS_PER_BAR: u32 = 4;
+    const SUBDIVISIONS: u32 = 2;
+
+    midi.measures.windows(2).flat_map(|bar| {
+        let start = bar[0];
+        let len = bar[1].saturating_sub(bar[0]);
+        let step = len / (BEATS_PER_BAR * SUBDIVISIONS);
+
+        (0..BEATS_PER_BAR * SUBDIVISIONS).map(move |i| Beat {
+            time: start + step * i,
+            strength: beat_strength(i, SUBDIVISIONS),
+        })
+    })
+}
+
+/// Classify the `i`-th tick within a bar: the first tick is the downbeat, every
+/// `subdivisions`-th tick is a full beat, everything else is a subdivision.
+fn beat_strength(i: u32, subdivisions: u32) -> BeatStrength {
+    if i == 0 {
+        BeatStrength::Bar
+    } else if i % subdivisions == 0 {
+        BeatStrength::Beat
+    } else {
+        BeatStrength::Subdivision
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn beat_strength_classifies_bar_beat_and_subdivision() {
+        const SUBDIVISIONS: u32 = 2;
+
+        // First tick of the bar is always the downbeat.
+        assert!(matches!(beat_strength(0, SUBDIVISIONS), BeatStrength::Bar));
+        // Even ticks land on a beat, odd ticks are subdivisions.
+        assert!(matches!(beat_strength(1, SUBDIVISIONS), BeatStrength::Subdivision));
+        assert!(matches!(beat_strength(2, SUBDIVISIONS), BeatStrength::Beat));
+        assert!(matches!(beat_strength(3, SUBDIVISIONS), BeatStrength::Subdivision));
+        assert!(matches!(beat_strength(4, SUBDIVISIONS), BeatStrength::Beat));
+    }
+}
@@ -0,0 +1,314 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{Receiver, Sender};
+use std::sync::Arc;
+use std::time::Duration;
+
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+
+use crate::target::Target;
+
+/// Absolute position in the output stream, measured in frames from the moment
+/// the mixer started. Using sample offsets rather than wall-clock lets voices
+/// fire precisely even when they are scheduled between audio callbacks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct SampleTime(pub u64);
+
+impl SampleTime {
+    fn from_secs(secs: f32, sample_rate: u32) -> Self {
+        Self((secs * sample_rate as f32) as u64)
+    }
+}
+
+/// A click handed from the update thread to the audio callback for playback at
+/// a precise sample offset.
+struct ScheduledClick {
+    sample: Arc<[f32]>,
+    start: SampleTime,
+}
+
+/// A one-shot click rendered into the output by the [`Mixer`].
+struct Voice {
+    sample: Arc<[f32]>,
+    start: SampleTime,
+    pos: usize,
+}
+
+/// Sums the active one-shot voices into the output stream at the device sample
+/// rate. Owned exclusively by the audio callback: new clicks arrive over an
+/// SPSC channel (no lock shared with the real-time thread) and the current
+/// playback position is published back through an atomic clock.
+pub struct Mixer {
+    voices: Vec<Voice>,
+    clock: u64,
+
+    incoming: Receiver<ScheduledClick>,
+    published_clock: Arc<AtomicU64>,
+}
+
+impl Mixer {
+    fn new(incoming: Receiver<ScheduledClick>, published_clock: Arc<AtomicU64>) -> Self {
+        Self {
+            voices: Vec::new(),
+            clock: 0,
+            incoming,
+            published_clock,
+        }
+    }
+
+    /// Drain everything the scheduler has queued since the last callback.
+    fn drain(&mut self) {
+        while let Ok(click) = self.incoming.try_recv() {
+            self.voices.push(Voice {
+                sample: click.sample,
+                start: click.start,
+                pos: 0,
+            });
+        }
+    }
+
+    /// Sum the active voices into `out` (interleaved, `channels` wide) and
+    /// advance the clock. The buffer is overwritten, not added to, so the mixer
+    /// can own a dedicated output stream. Finished voices are reaped once they
+    /// fall silent, and the advanced clock is published for the scheduler.
+    pub fn fill(&mut self, out: &mut [f32], channels: usize) {
+        self.drain();
+
+        for s in out.iter_mut() {
+            *s = 0.0;
+        }
+
+        let frames = out.len() / channels.max(1);
+
+        for frame in 0..frames {
+            let now = SampleTime(self.clock + frame as u64);
+            let mut acc = 0.0;
+
+            for voice in self.voices.iter_mut() {
+                if now < voice.start {
+                    continue;
+                }
+                if let Some(s) = voice.sample.get(voice.pos) {
+                    acc += *s;
+                    voice.pos += 1;
+                }
+            }
+
+            for ch in 0..channels {
+                out[frame * channels + ch] += acc;
+            }
+        }
+
+        self.clock += frames as u64;
+        self.voices
+            .retain(|voice| voice.pos < voice.sample.len() || SampleTime(self.clock) < voice.start);
+        self.published_clock.store(self.clock, Ordering::Release);
+    }
+}
+
+/// Built-in click track locked to the MIDI tempo map.
+pub struct Metronome {
+    enabled: bool,
+    sample_rate: u32,
+
+    downbeat: Arc<[f32]>,
+    beat: Arc<[f32]>,
+
+    // Lock-free handoff to the audio callback: clicks go out over `tx`, and the
+    // callback publishes its sample position through `clock` so scheduling never
+    // has to block the real-time thread.
+    tx: Sender<ScheduledClick>,
+    clock: Arc<AtomicU64>,
+    // The output stream kept alive for as long as the metronome exists; its
+    // callback calls `Mixer::fill`. `None` if no audio device is available.
+    _stream: Option<cpal::Stream>,
+
+    // Index of the next beat (counting from the start of the song) still to be
+    // scheduled, so each beat fires exactly once as the playhead sweeps past.
+    next_beat: u64,
+}
+
+impl Metronome {
+    pub fn new(sample_rate: u32) -> Self {
+        let (tx, rx) = std::sync::mpsc::channel();
+        let clock = Arc::new(AtomicU64::new(0));
+
+        let mixer = Mixer::new(rx, clock.clone());
+        let (stream, sample_rate) = open_stream(mixer, sample_rate);
+
+        Self {
+            enabled: false,
+            sample_rate,
+            downbeat: click(1760.0, sample_rate),
+            beat: click(880.0, sample_rate),
+            tx,
+            clock,
+            _stream: stream,
+            next_beat: 0,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn toggle(&mut self) -> bool {
+        self.enabled = !self.enabled;
+        self.enabled
+    }
+
+    /// Look ahead from `time` by the wall-clock frame `delta`, scaled here by
+    /// the config's speed multiplier, and schedule a click at every beat
+    /// boundary crossed this frame. `seconds_per_beat` comes from the tempo map at the current time.
+    pub fn update(&mut self, target: &Target, time: Duration, delta: Duration) {
+        if !self.enabled {
+            return;
+        }
+
+        let Some(midi) = target.midi_file.as_ref() else {
+            return;
+        };
+
+        let seconds_per_beat = midi.seconds_per_beat(time);
+        if seconds_per_beat <= 0.0 {
+            return;
+        }
+
+        let speed = target.config.speed_multiplier.max(f32::EPSILON);
+        let now = time.as_secs_f32();
+        let end = now + delta.as_secs_f32() * speed;
+
+        // Re-anchor if the user seeked backwards.
+        let current_beat = (now / seconds_per_beat) as u64;
+        if current_beat < self.next_beat {
+            self.next_beat = current_beat;
+        }
+
+        // The callback keeps this up to date; a slightly stale read only shifts
+        // the click by a frame, which the per-voice `start` offset absorbs.
+        let clock = self.clock.load(Ordering::Acquire);
+
+        while (self.next_beat as f32) * seconds_per_beat < end {
+            let beat_time = self.next_beat as f32 * seconds_per_beat;
+            if beat_time >= now {
+                let beats_per_bar = midi.beats_per_bar(time).max(1);
+                let sample = if self.next_beat % beats_per_bar as u64 == 0 {
+                    self.downbeat.clone()
+                } else {
+                    self.beat.clone()
+                };
+
+                let offset = (beat_time - now) / speed;
+                let start = SampleTime(clock + SampleTime::from_secs(offset, self.sample_rate).0);
+                self.tx.send(ScheduledClick { sample, start }).ok();
+            }
+            self.next_beat += 1;
+        }
+    }
+}
+
+/// Open a dedicated output stream whose callback drains and sums the mixer's
+/// voices into the device. Returns the stream (kept alive by the caller) and the
+/// device sample rate the clicks should be rendered at. On any audio error the
+/// stream is `None` and the requested `fallback_rate` is returned so the rest of
+/// the metronome still behaves deterministically.
+fn open_stream(mut mixer: Mixer, fallback_rate: u32) -> (Option<cpal::Stream>, u32) {
+    let host = cpal::default_host();
+    let Some(device) = host.default_output_device() else {
+        return (None, fallback_rate);
+    };
+    let Ok(config) = device.default_output_config() else {
+        return (None, fallback_rate);
+    };
+
+    let sample_rate = config.sample_rate().0;
+    let channels = config.channels() as usize;
+
+    let stream = device.build_output_stream(
+        &config.into(),
+        move |data: &mut [f32], _: &cpal::OutputCallbackInfo| {
+            mixer.fill(data, channels);
+        },
+        |err| log::error!("metronome output stream error: {err}"),
+        None,
+    );
+
+    match stream {
+        Ok(stream) => {
+            stream.play().ok();
+            (Some(stream), sample_rate)
+        }
+        Err(err) => {
+            log::error!("failed to open metronome output stream: {err}");
+            (None, fallback_rate)
+        }
+    }
+}
+
+/// Procedurally render a short decaying sine burst to use as a click.
+fn click(freq: f32, sample_rate: u32) -> Arc<[f32]> {
+    const LEN: Duration = Duration::from_millis(40);
+
+    let frames = (LEN.as_secs_f32() * sample_rate as f32) as usize;
+    let mut buf = Vec::with_capacity(frames);
+
+    for i in 0..frames {
+        let t = i as f32 / sample_rate as f32;
+        let envelope = (-t * 60.0).exp();
+        let phase = t * freq * std::f32::consts::TAU;
+        buf.push(phase.sin() * envelope * 0.5);
+    }
+
+    buf.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Build a mixer plus the sender feeding it, mirroring `Metronome::new`.
+    fn test_mixer() -> (Mixer, Sender<ScheduledClick>) {
+        let (tx, rx) = std::sync::mpsc::channel();
+        (Mixer::new(rx, Arc::new(AtomicU64::new(0))), tx)
+    }
+
+    #[test]
+    fn fill_mixes_sample_across_channels_and_reaps_finished_voice() {
+        let (mut mixer, tx) = test_mixer();
+        tx.send(ScheduledClick {
+            sample: Arc::from(vec![1.0, 0.5]),
+            start: SampleTime(0),
+        })
+        .unwrap();
+
+        let mut out = [0.0; 4]; // two stereo frames
+        mixer.fill(&mut out, 2);
+
+        // Each source sample is duplicated across the two channels.
+        assert_eq!(out, [1.0, 1.0, 0.5, 0.5]);
+        // The advanced clock is published for the scheduler to read.
+        assert_eq!(mixer.published_clock.load(Ordering::Acquire), 2);
+
+        // The voice is exhausted, so it must have been reaped and leave silence.
+        assert!(mixer.voices.is_empty());
+        let mut after = [0.0; 2];
+        mixer.fill(&mut after, 2);
+        assert_eq!(after, [0.0, 0.0]);
+    }
+
+    #[test]
+    fn fill_defers_a_voice_until_its_start_sample() {
+        let (mut mixer, tx) = test_mixer();
+        tx.send(ScheduledClick {
+            sample: Arc::from(vec![1.0]),
+            start: SampleTime(1),
+        })
+        .unwrap();
+
+        let mut out = [0.0; 2]; // two mono frames
+        mixer.fill(&mut out, 1);
+
+        // Silent on the first frame, fires exactly on its scheduled sample.
+        assert_eq!(out, [0.0, 1.0]);
+        assert!(mixer.voices.is_empty());
+    }
+}
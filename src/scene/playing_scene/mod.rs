@@ -17,28 +17,49 @@ use midi_player::MidiPlayer;
 mod toast_manager;
 use toast_manager::ToastManager;
 
+mod guidelines;
+use guidelines::Guidelines;
+
+mod metronome;
+use metronome::Metronome;
+
+mod layout;
+use layout::get_layout;
+
+mod background;
+use background::AnimatedBackground;
+
+// Z-ordered layers of the shared quad pipeline, drawn back to front.
+const LAYER_BACKGROUND: usize = 0;
+const LAYER_GUIDELINES: usize = 1;
+const LAYER_PROGRESSBAR: usize = 2;
+
+// Height of the clickable transport strip at the top of the window. A little
+// taller than the drawn bar so it stays easy to grab.
+const PROGRESSBAR_GRAB_HEIGHT: f32 = 20.0;
+
 pub struct PlayingScene {
     keyboard_layout: piano_math::KeyboardLayout,
 
     piano_keyboard: KeyboardRenderer,
     notes: WaterfallRenderer,
+    guidelines: Guidelines,
+    background: AnimatedBackground,
 
     player: MidiPlayer,
     quad_pipeline: QuadPipeline,
     toast_manager: ToastManager,
-}
+    metronome: Metronome,
 
-fn get_layout(width: f32, height: f32) -> piano_math::KeyboardLayout {
-    let white_count = piano_math::KeyboardRange::standard_88_keys().white_count();
-    let neutral_width = width / white_count as f32;
-    let neutral_height = height * 0.2;
-
-    piano_math::standard_88_keys(neutral_width, neutral_height)
+    cursor_pos: (f32, f32),
+    is_scrubbing: bool,
 }
 
 impl PlayingScene {
     pub fn new(target: &mut Target) -> Self {
         let keyboard_layout = get_layout(
+            &target.config,
+            target.midi_file.as_ref(),
             target.window_state.logical_size.width,
             target.window_state.logical_size.height,
         );
@@ -62,22 +83,68 @@ impl PlayingScene {
         let player = MidiPlayer::new(target);
         notes.update(&target.gpu.queue, player.time_without_lead_in());
 
+        let guidelines = Guidelines::new(keyboard_layout.clone(), &target.config);
+
+        let background = AnimatedBackground::new(
+            &target.config,
+            target.window_state.logical_size.width,
+            target.window_state.logical_size.height,
+        );
+
+        // The metronome opens its own output stream at the device sample rate;
+        // the player's output rate is only used when no audio device is found.
+        let metronome = Metronome::new(player.output_sample_rate());
+
+        let mut quad_pipeline = QuadPipeline::new(&target.gpu, &target.transform_uniform);
+        quad_pipeline.init_layer(&target.gpu, AnimatedBackground::instance_capacity()); // LAYER_BACKGROUND
+        quad_pipeline.init_layer(&target.gpu, 256); // LAYER_GUIDELINES
+        quad_pipeline.init_layer(&target.gpu, 1); // LAYER_PROGRESSBAR
+
         Self {
             keyboard_layout,
 
             piano_keyboard,
             notes,
+            guidelines,
+            background,
             player,
-            quad_pipeline: QuadPipeline::new(&target.gpu, &target.transform_uniform),
+            quad_pipeline,
 
             toast_manager: ToastManager::default(),
+            metronome,
+
+            cursor_pos: (0.0, 0.0),
+            is_scrubbing: false,
         }
     }
 
+    /// Move playback to `percentage` of the song and bring every stateful view
+    /// back in sync with the new position.
+    fn seek_to(&mut self, target: &mut Target, percentage: f32) {
+        let percentage = percentage.clamp(0.0, 1.0);
+        self.player.set_percentage(target, percentage);
+
+        self.notes.update(
+            &target.gpu.queue,
+            self.player.time_without_lead_in() + target.config.playback_offset,
+        );
+        self.piano_keyboard.reset_notes();
+    }
+
+    /// Seek to wherever the cursor sits horizontally and surface the target
+    /// time as a toast while the bar is being dragged.
+    fn scrub_to_cursor(&mut self, target: &mut Target) {
+        let percentage = self.cursor_pos.0 / target.window_state.logical_size.width;
+        self.seek_to(target, percentage);
+        self.toast_manager
+            .seek_toast(self.player.time_without_lead_in());
+    }
+
     fn update_progresbar(&mut self, target: &mut Target) {
         let size_x = target.window_state.logical_size.width * self.player.percentage();
         self.quad_pipeline.update_instance_buffer(
             &target.gpu.queue,
+            LAYER_PROGRESSBAR,
             vec![QuadInstance {
                 position: [0.0, 0.0],
                 size: [size_x, 5.0],
@@ -86,6 +153,13 @@ impl PlayingScene {
             }],
         );
     }
+
+    fn update_guidelines(&mut self, target: &mut Target) {
+        let time = self.player.time_without_lead_in() + target.config.playback_offset;
+        let instances = self.guidelines.update(target, time).to_vec();
+        self.quad_pipeline
+            .update_instance_buffer(&target.gpu.queue, LAYER_GUIDELINES, instances);
+    }
 }
 
 impl Scene for PlayingScene {
@@ -99,6 +173,8 @@ impl Scene for PlayingScene {
 
     fn resize(&mut self, target: &mut Target) {
         self.keyboard_layout = get_layout(
+            &target.config,
+            target.midi_file.as_ref(),
             target.window_state.logical_size.width,
             target.window_state.logical_size.height,
         );
@@ -107,6 +183,13 @@ impl Scene for PlayingScene {
         self.piano_keyboard
             .position_on_bottom_of_parent(target.window_state.logical_size.height);
 
+        self.guidelines.set_layout(self.keyboard_layout.clone());
+
+        self.background.resize(
+            target.window_state.logical_size.width,
+            target.window_state.logical_size.height,
+        );
+
         self.notes.resize(
             &target.gpu.queue,
             target.midi_file.as_ref().unwrap(),
@@ -128,13 +211,27 @@ impl Scene for PlayingScene {
             }
         }
 
+        let background = self
+            .background
+            .update(delta, self.player.time_without_lead_in())
+            .to_vec();
+        self.quad_pipeline
+            .update_instance_buffer(&target.gpu.queue, LAYER_BACKGROUND, background);
+
         self.update_progresbar(target);
+        self.update_guidelines(target);
 
         self.notes.update(
             &target.gpu.queue,
             self.player.time_without_lead_in() + target.config.playback_offset,
         );
 
+        self.metronome.update(
+            target,
+            self.player.time_without_lead_in() + target.config.playback_offset,
+            delta,
+        );
+
         self.piano_keyboard
             .update(&target.gpu.queue, target.text_renderer.glyph_brush());
         self.toast_manager.update(target);
@@ -157,14 +254,23 @@ impl Scene for PlayingScene {
                 depth_stencil_attachment: None,
             });
 
+        // Background and guidelines sit behind the waterfall, so draw those
+        // layers first.
+        self.quad_pipeline
+            .render(LAYER_BACKGROUND, &target.transform_uniform, &mut render_pass);
+        self.quad_pipeline
+            .render(LAYER_GUIDELINES, &target.transform_uniform, &mut render_pass);
+
         self.notes
             .render(&target.transform_uniform, &mut render_pass);
 
         self.piano_keyboard
             .render(&target.transform_uniform, &mut render_pass);
 
+        // The progress bar is the scrub playhead and must stay on top of the
+        // waterfall and keyboard, so its layer is drawn last.
         self.quad_pipeline
-            .render(&target.transform_uniform, &mut render_pass)
+            .render(LAYER_PROGRESSBAR, &target.transform_uniform, &mut render_pass);
     }
 
     fn window_event(&mut self, target: &mut Target, event: &WindowEvent) {
@@ -185,14 +291,38 @@ impl Scene for PlayingScene {
                         Some(VirtualKeyCode::Space) => {
                             self.player.pause_resume();
                         }
+                        Some(VirtualKeyCode::M) => {
+                            let on = self.metronome.toggle();
+                            self.toast_manager.metronome_toast(on);
+                        }
                         _ => {}
                     }
                 }
             }
             MouseInput { state, button, .. } => {
+                if *button == winit::event::MouseButton::Left {
+                    match state {
+                        ElementState::Pressed if self.cursor_pos.1 <= PROGRESSBAR_GRAB_HEIGHT => {
+                            self.is_scrubbing = true;
+                            self.scrub_to_cursor(target);
+                        }
+                        ElementState::Released => {
+                            self.is_scrubbing = false;
+                        }
+                        _ => {}
+                    }
+                }
+
                 self.player.mouse_input(target, state, button);
             }
             CursorMoved { position, .. } => {
+                let scale = target.window_state.scale_factor as f32;
+                self.cursor_pos = (position.x as f32 / scale, position.y as f32 / scale);
+
+                if self.is_scrubbing {
+                    self.scrub_to_cursor(target);
+                }
+
                 self.player.handle_cursor_moved(target, position);
             }
             _ => {}